@@ -0,0 +1,271 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A local fee-rate oracle, driven entirely by what `TransactionPool` itself
+//! observes: which feerate a transaction entered the pool with, and how
+//! many blocks later (if ever) `reconcile_block` saw it confirmed. Wallets
+//! can query `estimate_fee` instead of guessing a fixed feerate.
+//!
+//! The approach mirrors Bitcoin Core's `TxConfirmStats`: feerates are
+//! bucketed exponentially, and for a handful of confirmation-target
+//! horizons we keep a decaying count of "confirmed within the horizon" vs
+//! "total observed at the horizon" per bucket. `estimate_fee` then returns
+//! the cheapest bucket whose observed success rate at the requested horizon
+//! clears the configured threshold.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use gotts_core::core::Transaction;
+use gotts_util::secp::pedersen::Commitment;
+
+/// Confirmation-target horizons (in blocks) tracked by the estimator.
+const HORIZONS: [u64; 6] = [1, 3, 6, 12, 24, 48];
+
+/// Feerates are tracked as fee-per-`FEERATE_SCALE`-weight rather than
+/// fee-per-weight: most real transactions pay a fee smaller than their
+/// weight, so a plain `fee / weight` truncates to `0` for nearly everything
+/// and collapses every bucket into one. Scaling up first keeps that
+/// division meaningful.
+const FEERATE_SCALE: u64 = 1_000;
+
+/// Configuration for the fee estimator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeEstimatorConfig {
+	/// Smallest feerate bucket boundary, in fee per `FEERATE_SCALE` weight.
+	pub min_feerate: u64,
+	/// Largest feerate bucket boundary, in fee per `FEERATE_SCALE` weight.
+	pub max_feerate: u64,
+	/// Growth factor between consecutive bucket boundaries.
+	pub bucket_growth: f64,
+	/// Per-block decay applied to historical counts, so old observations
+	/// gradually matter less than recent ones.
+	pub decay: f64,
+	/// Minimum observed success rate, at the requested horizon, for a
+	/// bucket's feerate to be considered "safe" by `estimate_fee`.
+	pub success_threshold: f64,
+}
+
+impl Default for FeeEstimatorConfig {
+	fn default() -> FeeEstimatorConfig {
+		FeeEstimatorConfig {
+			min_feerate: 1,
+			max_feerate: 1_000_000,
+			bucket_growth: 1.1,
+			decay: 0.998,
+			success_threshold: 0.85,
+		}
+	}
+}
+
+/// Decaying "confirmed within horizon" vs "total observed at horizon"
+/// counts for a single feerate bucket.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct HorizonStats {
+	confirmed: f64,
+	total: f64,
+}
+
+impl HorizonStats {
+	fn success_rate(&self) -> f64 {
+		if self.total <= 0.0 {
+			0.0
+		} else {
+			self.confirmed / self.total
+		}
+	}
+}
+
+/// A transaction the estimator is waiting to see confirmed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrackedTx {
+	/// Fee per `FEERATE_SCALE` weight, see `FEERATE_SCALE`.
+	feerate: u64,
+	entry_height: u64,
+}
+
+/// Rolling history used by the estimator, the part that needs to survive a
+/// restart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FeeHistory {
+	/// `stats[bucket_idx][horizon]`.
+	stats: Vec<HashMap<u64, HorizonStats>>,
+}
+
+/// A local fee-rate estimator, fed by the pool's own `reconcile_block`
+/// observations.
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+	config: FeeEstimatorConfig,
+	/// Ascending upper-bound feerate for each bucket.
+	buckets: Vec<u64>,
+	history: FeeHistory,
+	/// Transactions currently in the pool, awaiting confirmation.
+	tracked: HashMap<Commitment, TrackedTx>,
+}
+
+impl FeeEstimator {
+	/// Build a new, empty estimator from `config`.
+	pub fn new(config: FeeEstimatorConfig) -> FeeEstimator {
+		let buckets = Self::build_buckets(&config);
+		let history = FeeHistory {
+			stats: vec![HashMap::new(); buckets.len()],
+		};
+		FeeEstimator {
+			config,
+			buckets,
+			history,
+			tracked: HashMap::new(),
+		}
+	}
+
+	fn build_buckets(config: &FeeEstimatorConfig) -> Vec<u64> {
+		let mut buckets = Vec::new();
+		let mut bound = config.min_feerate as f64;
+		while (bound as u64) < config.max_feerate {
+			buckets.push(bound as u64);
+			bound *= config.bucket_growth;
+		}
+		buckets.push(config.max_feerate);
+		buckets
+	}
+
+	/// Index of the bucket whose upper bound is the smallest one `>=
+	/// feerate` (the last bucket catches anything above `max_feerate`).
+	fn bucket_index(&self, feerate: u64) -> usize {
+		self.buckets
+			.iter()
+			.position(|&bound| feerate <= bound)
+			.unwrap_or_else(|| self.buckets.len() - 1)
+	}
+
+	/// Start tracking `tx`, entering the pool at `entry_height`, keyed by
+	/// its first kernel's excess (transactions always carry at least one
+	/// kernel once fully built).
+	pub fn track(&mut self, tx: &Transaction, entry_height: u64) {
+		if let Some(kernel) = tx.kernels().first() {
+			let weight = tx.weight() as u64;
+			// Scale up before dividing: most transactions pay a fee smaller
+			// than their weight, so a bare `fee / weight` truncates to `0`
+			// for nearly everything and every tx lands in the same bucket.
+			let feerate = if weight == 0 {
+				0
+			} else {
+				tx.fee() * FEERATE_SCALE / weight
+			};
+			self.tracked.insert(
+				kernel.excess,
+				TrackedTx {
+					feerate,
+					entry_height,
+				},
+			);
+		}
+	}
+
+	/// Stop tracking a tx without recording any observation, e.g. because
+	/// it was evicted by a replacement rather than mined.
+	pub fn untrack(&mut self, tx: &Transaction) {
+		if let Some(kernel) = tx.kernels().first() {
+			self.tracked.remove(&kernel.excess);
+		}
+	}
+
+	/// Record the outcome of processing a new block at `height`: every
+	/// still-tracked tx either got confirmed in it (`confirmed` holds its
+	/// kernel excess) or is now one block older.
+	pub fn process_block(&mut self, height: u64, confirmed: &[Commitment]) {
+		for stats in self.history.stats.iter_mut() {
+			for horizon_stats in stats.values_mut() {
+				horizon_stats.confirmed *= self.config.decay;
+				horizon_stats.total *= self.config.decay;
+			}
+		}
+
+		let confirmed_now: HashMap<Commitment, TrackedTx> = confirmed
+			.iter()
+			.filter_map(|excess| self.tracked.remove(excess).map(|t| (*excess, t)))
+			.collect();
+
+		for tracked in confirmed_now.values() {
+			let blocks_to_confirm = height.saturating_sub(tracked.entry_height).max(1);
+			let bucket = self.bucket_index(tracked.feerate);
+			for &horizon in HORIZONS.iter() {
+				if horizon >= blocks_to_confirm {
+					let entry = self.history.stats[bucket].entry(horizon).or_default();
+					entry.confirmed += 1.0;
+					entry.total += 1.0;
+				}
+			}
+		}
+
+		for tracked in self.tracked.values() {
+			let age = height.saturating_sub(tracked.entry_height);
+			let bucket = self.bucket_index(tracked.feerate);
+			for &horizon in HORIZONS.iter() {
+				if age == horizon {
+					let entry = self.history.stats[bucket].entry(horizon).or_default();
+					entry.total += 1.0;
+				}
+			}
+		}
+	}
+
+	/// The lowest feerate (in fee per `FEERATE_SCALE` weight, see
+	/// `FEERATE_SCALE`) whose observed success rate, at the smallest tracked
+	/// horizon `>= target_blocks` (the largest horizon, 48, for targets
+	/// beyond it), clears `success_threshold`; the value returned is that
+	/// bucket's upper bound. Returns `None` if no bucket with history at
+	/// that horizon clears the threshold yet.
+	pub fn estimate_fee(&self, target_blocks: u64) -> Option<u64> {
+		let horizon = *HORIZONS
+			.iter()
+			.find(|&&h| h >= target_blocks)
+			.unwrap_or_else(|| HORIZONS.last().unwrap());
+
+		for (idx, bucket_stats) in self.history.stats.iter().enumerate() {
+			if let Some(stats) = bucket_stats.get(&horizon) {
+				if stats.total >= 1.0 && stats.success_rate() >= self.config.success_threshold {
+					return Some(self.buckets[idx]);
+				}
+			}
+		}
+		None
+	}
+
+	/// Persist the rolling history (but not the in-flight `tracked` set) so
+	/// estimates survive a restart.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+		let file = File::create(path).map_err(|e| e.to_string())?;
+		serde_json::to_writer(BufWriter::new(file), &self.history).map_err(|e| e.to_string())
+	}
+
+	/// Load previously persisted history back into a fresh estimator.
+	pub fn load<P: AsRef<Path>>(config: FeeEstimatorConfig, path: P) -> Result<FeeEstimator, String> {
+		let file = File::open(path).map_err(|e| e.to_string())?;
+		let history: FeeHistory =
+			serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+		let buckets = Self::build_buckets(&config);
+		Ok(FeeEstimator {
+			config,
+			buckets,
+			history,
+			tracked: HashMap::new(),
+		})
+	}
+}
@@ -0,0 +1,39 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The gotts transaction pool, as the name implies, keeps the unconfirmed
+//! transactions that may eventually be included in a future block, and
+//! exposes them to other modules (network relay, block assembly, etc.)
+
+#![deny(non_upper_case_globals)]
+#![deny(non_camel_case_types)]
+#![deny(non_snake_case)]
+#![deny(unused_mut)]
+#![warn(missing_docs)]
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod fee_estimator;
+pub mod pool;
+pub mod transaction_pool;
+pub mod types;
+pub mod validation;
+
+pub use crate::fee_estimator::{FeeEstimator, FeeEstimatorConfig};
+pub use crate::pool::Pool;
+pub use crate::transaction_pool::TransactionPool;
+pub use crate::types::{BlockChain, PoolConfig, PoolEntry, PoolError, TxSource};
+pub use crate::validation::{AggregateBodyValidator, PoolBodyValidation};
@@ -0,0 +1,354 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The pool itself, a container of transactions plus the logic needed to
+//! walk the dependency graph they form (which entry spends which other
+//! entry's output) and to order them for block assembly.
+
+use std::collections::{HashMap, HashSet};
+
+use gotts_core::core::Transaction;
+use gotts_util::secp::pedersen::Commitment;
+
+use crate::types::{PoolEntry, PoolError};
+
+/// A container of `PoolEntry`, along with the graph bookkeeping needed to
+/// find, for any entry, the set of other entries it depends on (its
+/// "unconfirmed ancestors") or that depend on it (its "unconfirmed
+/// descendants").
+#[derive(Clone, Default)]
+pub struct Pool {
+	/// Entries currently tracked by the pool, in the order they were added.
+	pub entries: Vec<PoolEntry>,
+}
+
+impl Pool {
+	/// Instantiate a new empty pool.
+	pub fn new() -> Pool {
+		Pool {
+			entries: Vec::new(),
+		}
+	}
+
+	/// Number of transactions currently held.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Whether the pool is empty.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Add a new entry, assumed to have already been validated.
+	pub fn add_to_pool(&mut self, entry: PoolEntry) {
+		self.entries.push(entry);
+	}
+
+	/// Remove and return the entries at the given indices, in descending
+	/// index order so earlier removals don't shift later indices.
+	pub fn remove_by_indices(&mut self, mut idxs: Vec<usize>) -> Vec<PoolEntry> {
+		idxs.sort_unstable_by(|a, b| b.cmp(a));
+		idxs.dedup();
+		idxs.into_iter().map(|i| self.entries.remove(i)).collect()
+	}
+
+	/// Indices of entries that conflict with `tx`, i.e. that spend at least
+	/// one input also spent by `tx`.
+	pub fn find_conflicting_entries(&self, tx: &Transaction) -> Vec<usize> {
+		let spent: HashSet<Commitment> = tx.inputs().iter().map(|i| i.commit).collect();
+		self.entries
+			.iter()
+			.enumerate()
+			.filter(|(_, entry)| entry.tx.inputs().iter().any(|i| spent.contains(&i.commit)))
+			.map(|(idx, _)| idx)
+			.collect()
+	}
+
+	/// The index of the pool entry that created output `commit`, if any.
+	pub fn owner_of(&self, commit: &Commitment) -> Option<usize> {
+		self.output_owners().get(commit).cloned()
+	}
+
+	/// Map of output commitment -> index of the entry that creates it. Used
+	/// to walk the in-pool dependency graph: an entry spending a commitment
+	/// present in this map depends on the owning entry.
+	fn output_owners(&self) -> HashMap<Commitment, usize> {
+		let mut owners = HashMap::new();
+		for (idx, entry) in self.entries.iter().enumerate() {
+			for output in entry.tx.outputs() {
+				owners.insert(output.commit, idx);
+			}
+		}
+		owners
+	}
+
+	/// The indices of the entries directly spent by entry `idx`, restricted
+	/// to `scope`.
+	fn direct_ancestors(
+		&self,
+		idx: usize,
+		owners: &HashMap<Commitment, usize>,
+		scope: &HashSet<usize>,
+	) -> Vec<usize> {
+		self.entries[idx]
+			.tx
+			.inputs()
+			.iter()
+			.filter_map(|input| owners.get(&input.commit))
+			.cloned()
+			.filter(|parent| scope.contains(parent))
+			.collect()
+	}
+
+	/// The full set of in-pool ancestors of entry `idx` (transitively, every
+	/// pool entry it directly or indirectly spends from), restricted to
+	/// `scope`. Does not include `idx` itself.
+	pub fn unconfirmed_ancestors(&self, idx: usize, scope: &HashSet<usize>) -> HashSet<usize> {
+		self.unconfirmed_ancestors_with(idx, scope, &self.output_owners())
+	}
+
+	/// Same as `unconfirmed_ancestors`, but takes a pre-built `output_owners`
+	/// map so callers walking many entries (e.g. `cpfp_order`) build it once
+	/// rather than once per entry.
+	fn unconfirmed_ancestors_with(
+		&self,
+		idx: usize,
+		scope: &HashSet<usize>,
+		owners: &HashMap<Commitment, usize>,
+	) -> HashSet<usize> {
+		let mut ancestors = HashSet::new();
+		let mut stack = self.direct_ancestors(idx, owners, scope);
+		while let Some(parent) = stack.pop() {
+			if ancestors.insert(parent) {
+				stack.extend(self.direct_ancestors(parent, owners, scope));
+			}
+		}
+		ancestors
+	}
+
+	/// The full set of in-pool descendants of entry `idx` (transitively,
+	/// every pool entry that directly or indirectly spends one of its
+	/// outputs). Does not include `idx` itself.
+	pub fn unconfirmed_descendants(&self, idx: usize) -> HashSet<usize> {
+		let owners = self.output_owners();
+		let all: HashSet<usize> = (0..self.entries.len()).collect();
+		all.into_iter()
+			.filter(|&i| {
+				i != idx && self
+					.unconfirmed_ancestors_with(i, &all, &owners)
+					.contains(&idx)
+			})
+			.collect()
+	}
+
+	/// Order `idxs` (assumed to be closed under `unconfirmed_ancestors`, i.e.
+	/// every ancestor present in the set is also in `idxs`) so that every
+	/// entry appears after all the entries it depends on.
+	pub fn topo_sort(&self, idxs: &HashSet<usize>) -> Vec<usize> {
+		self.topo_sort_with(idxs, &self.output_owners())
+	}
+
+	/// Same as `topo_sort`, but takes a pre-built `output_owners` map.
+	fn topo_sort_with(
+		&self,
+		idxs: &HashSet<usize>,
+		owners: &HashMap<Commitment, usize>,
+	) -> Vec<usize> {
+		let mut placed: HashSet<usize> = HashSet::new();
+		let mut ordered = Vec::with_capacity(idxs.len());
+		while ordered.len() < idxs.len() {
+			let next = *idxs
+				.iter()
+				.find(|&&i| {
+					!placed.contains(&i)
+						&& self
+							.direct_ancestors(i, owners, idxs)
+							.iter()
+							.all(|a| placed.contains(a))
+				})
+				.expect("cycle in unconfirmed ancestor graph");
+			placed.insert(next);
+			ordered.push(next);
+		}
+		ordered
+	}
+
+	/// Select, in order, the pool entries to include in a new block so as to
+	/// maximize the fee collected given a `max_weight` budget.
+	///
+	/// Every entry is considered together with its full chain of in-pool
+	/// ancestors (its "package"), ranked by package feerate (package fee
+	/// divided by package weight). The highest-feerate package is added in
+	/// full, in topological order, and removed from consideration; the
+	/// remaining packages' feerates are then recomputed (any of their
+	/// ancestors that just got included are no longer double-counted) before
+	/// picking the next one. This lets a low-fee parent ride into the block
+	/// on the back of a high-fee child (child-pays-for-parent), which a
+	/// purely topological ordering can't express.
+	pub fn cpfp_order(&self, max_weight: usize) -> Vec<usize> {
+		// `entries` never changes over the course of this call (only
+		// `remaining` shrinks as packages get selected), so the output ->
+		// owning-entry map is invariant for the whole run and only needs
+		// building once, rather than once per candidate per pass.
+		let owners = self.output_owners();
+
+		let mut remaining: HashSet<usize> = (0..self.entries.len()).collect();
+		let mut remaining_weight = max_weight;
+		let mut selected: Vec<usize> = Vec::new();
+
+		// (package, fee, weight, package in topological order), keyed by the
+		// entry the package is rooted at. An entry's package can only change
+		// between passes if one of its ancestors was pulled out of
+		// `remaining` by a prior pass, so entries untouched by that keep
+		// their cached package instead of re-walking the ancestor graph and
+		// re-summing fee/weight from scratch every pass.
+		let mut package_cache: HashMap<usize, (HashSet<usize>, u64, usize, Vec<usize>)> =
+			HashMap::new();
+
+		loop {
+			if remaining.is_empty() {
+				break;
+			}
+
+			package_cache.retain(|_, (package, ..)| package.is_subset(&remaining));
+
+			let mut candidates: Vec<(u64, usize, Vec<usize>)> = Vec::with_capacity(remaining.len());
+			for &idx in &remaining {
+				let (fee, weight, topo) = if let Some((_, fee, weight, topo)) =
+					package_cache.get(&idx)
+				{
+					(*fee, *weight, topo.clone())
+				} else {
+					let mut package = self.unconfirmed_ancestors_with(idx, &remaining, &owners);
+					package.insert(idx);
+					let (fee, weight) = package.iter().fold((0u64, 0usize), |(f, w), &i| {
+						(
+							f + self.entries[i].tx.fee(),
+							w + self.entries[i].tx.weight(),
+						)
+					});
+					let topo = self.topo_sort_with(&package, &owners);
+					package_cache.insert(idx, (package, fee, weight, topo.clone()));
+					(fee, weight, topo)
+				};
+				candidates.push((fee, weight, topo));
+			}
+			// Rank by package feerate (fee / weight) without actually
+			// dividing: integer division would truncate every realistic
+			// fee/weight pair down to the same 0, making the ordering a
+			// no-op. Cross-multiply in `u128` instead so the comparison
+			// stays exact and can't overflow.
+			candidates.sort_by(|(a_fee, a_weight, _), (b_fee, b_weight, _)| {
+				(*b_fee as u128 * *a_weight as u128).cmp(&(*a_fee as u128 * *b_weight as u128))
+			});
+
+			let mut included = false;
+			for (_, weight, package) in candidates {
+				if package.iter().any(|i| !remaining.contains(i)) {
+					// Already pulled in as part of an earlier, higher-ranked
+					// package this round.
+					continue;
+				}
+				if weight > remaining_weight {
+					// Doesn't fit yet; try the next best package instead.
+					continue;
+				}
+				for &i in &package {
+					remaining.remove(&i);
+					package_cache.remove(&i);
+				}
+				remaining_weight -= weight;
+				selected.extend(package);
+				included = true;
+				break;
+			}
+
+			if !included {
+				// Nothing left fits in the remaining weight budget.
+				break;
+			}
+		}
+
+		selected
+	}
+}
+
+/// Order an arbitrary bundle of transactions, not yet part of any `Pool`,
+/// into an acceptance order where every member appears after every other
+/// member whose output it spends.
+///
+/// Returns `PoolError::InvalidPackage` if the members don't form a single
+/// connected ancestor set (e.g. an unrelated transaction got bundled in) or
+/// if they contain a cycle (which can't happen through valid in-package
+/// spends alone, but is checked defensively).
+pub fn order_package(txs: &[Transaction]) -> Result<Vec<usize>, PoolError> {
+	if txs.is_empty() {
+		return Err(PoolError::InvalidPackage);
+	}
+
+	let mut owners: HashMap<Commitment, usize> = HashMap::new();
+	for (idx, tx) in txs.iter().enumerate() {
+		for output in tx.outputs() {
+			owners.insert(output.commit, idx);
+		}
+	}
+
+	let mut direct_parents: Vec<HashSet<usize>> = vec![HashSet::new(); txs.len()];
+	for (idx, tx) in txs.iter().enumerate() {
+		for input in tx.inputs() {
+			if let Some(&parent) = owners.get(&input.commit) {
+				if parent != idx {
+					direct_parents[idx].insert(parent);
+				}
+			}
+		}
+	}
+
+	// The package must be a single connected component: every member must be
+	// reachable from every other member via a direct spend relationship,
+	// otherwise this isn't a dependency bundle but a grab-bag of unrelated
+	// transactions.
+	let mut adjacency: Vec<HashSet<usize>> = vec![HashSet::new(); txs.len()];
+	for (idx, parents) in direct_parents.iter().enumerate() {
+		for &parent in parents {
+			adjacency[idx].insert(parent);
+			adjacency[parent].insert(idx);
+		}
+	}
+	let mut seen = HashSet::new();
+	let mut stack = vec![0usize];
+	while let Some(node) = stack.pop() {
+		if seen.insert(node) {
+			stack.extend(adjacency[node].iter().cloned());
+		}
+	}
+	if seen.len() != txs.len() {
+		return Err(PoolError::InvalidPackage);
+	}
+
+	let mut placed: HashSet<usize> = HashSet::new();
+	let mut ordered = Vec::with_capacity(txs.len());
+	while ordered.len() < txs.len() {
+		let next = (0..txs.len())
+			.find(|idx| {
+				!placed.contains(idx) && direct_parents[*idx].iter().all(|p| placed.contains(p))
+			})
+			.ok_or(PoolError::InvalidPackage)?;
+		placed.insert(next);
+		ordered.push(next);
+	}
+	Ok(ordered)
+}
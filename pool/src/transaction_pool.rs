@@ -0,0 +1,402 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The top level transaction pool, the public interface used by the rest of
+//! the node: submitting new transactions, reconciling against newly mined
+//! blocks, and preparing the set of transactions a new block should include.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use gotts_core::core::verifier_cache::VerifierCache;
+use gotts_core::core::{transaction, Block, BlockHeader, OutputEx, Transaction, Weighting};
+use gotts_util::secp::pedersen::Commitment;
+use gotts_util::RwLock;
+
+use crate::fee_estimator::{FeeEstimator, FeeEstimatorConfig};
+use crate::pool::{self, Pool};
+use crate::types::{BlockChain, PoolConfig, PoolEntry, PoolError, TxSource};
+use crate::validation::{AggregateBodyValidator, PoolBodyValidation};
+
+/// The transaction pool, tracking unconfirmed transactions and exposing
+/// them to the rest of the node.
+pub struct TransactionPool<B> {
+	/// Pool configuration.
+	pub config: PoolConfig,
+	/// The pool itself, holding the transactions and the graph bookkeeping.
+	pub pool: Pool,
+	/// The chain adapter, used to validate transactions against the current
+	/// UTXO set.
+	pub blockchain: Arc<B>,
+	/// Shared cache of already-verified range proofs and kernel signatures.
+	pub verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+	/// Local fee-rate oracle, fed by every `add_to_pool`/`reconcile_block`
+	/// call this pool handles.
+	pub fee_estimator: FeeEstimator,
+	/// Validates every aggregate body this pool accepts, shared between
+	/// `add_to_pool`/`add_package_to_pool` and `reconcile_block`.
+	pub body_validator: AggregateBodyValidator,
+}
+
+impl<B> TransactionPool<B>
+where
+	B: BlockChain,
+{
+	/// Create a new transaction pool.
+	pub fn new(
+		config: PoolConfig,
+		blockchain: Arc<B>,
+		verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+	) -> TransactionPool<B> {
+		TransactionPool {
+			config,
+			pool: Pool::new(),
+			blockchain,
+			body_validator: AggregateBodyValidator {
+				verifier_cache: verifier_cache.clone(),
+			},
+			verifier_cache,
+			fee_estimator: FeeEstimator::new(FeeEstimatorConfig::default()),
+		}
+	}
+
+	/// Number of transactions currently held in the pool.
+	pub fn total_size(&self) -> usize {
+		self.pool.len()
+	}
+
+	/// Attempt to add a single transaction to the pool.
+	///
+	/// `stem` is reserved for a future stem/fluff (Dandelion) integration;
+	/// every transaction is fluffed directly into the pool today.
+	pub fn add_to_pool(
+		&mut self,
+		src: TxSource,
+		tx: Transaction,
+		_stem: bool,
+		header: &BlockHeader,
+	) -> Result<(), PoolError> {
+		if self.pool.len() >= self.config.max_pool_size {
+			return Err(PoolError::OverCapacity);
+		}
+
+		if self
+			.pool
+			.entries
+			.iter()
+			.any(|entry| entry.tx.kernels() == tx.kernels())
+		{
+			return Err(PoolError::DuplicateTx);
+		}
+
+		let conflicts = self.pool.find_conflicting_entries(&tx);
+		let to_evict = if conflicts.is_empty() {
+			Vec::new()
+		} else {
+			self.replaceable_set(&tx, &conflicts)?
+		};
+
+		self.validate_tx(&tx, header)?;
+
+		for &idx in &to_evict {
+			self.fee_estimator.untrack(&self.pool.entries[idx].tx);
+		}
+		self.pool.remove_by_indices(to_evict);
+		self.fee_estimator.track(&tx, header.height);
+		self.pool.add_to_pool(PoolEntry::new(tx, src));
+		Ok(())
+	}
+
+	/// Check whether `tx`, which directly conflicts with the pool entries in
+	/// `conflicts` (it double-spends at least one of their inputs), is a
+	/// valid BIP125-style replacement for them. Returns the full set of
+	/// entries (`conflicts` plus their in-pool descendants) that must be
+	/// evicted if `tx` is accepted.
+	///
+	/// A replacement is only valid if: it pays a strictly higher absolute
+	/// fee than everything it would evict; its feerate is strictly higher
+	/// than the evicted set's aggregate feerate; and it doesn't newly depend
+	/// on some other in-pool transaction outside the set it's evicting.
+	fn replaceable_set(&self, tx: &Transaction, conflicts: &[usize]) -> Result<Vec<usize>, PoolError> {
+		let mut evicted: HashSet<usize> = conflicts.iter().cloned().collect();
+		for &idx in conflicts {
+			evicted.extend(self.pool.unconfirmed_descendants(idx));
+		}
+
+		let evicted_fee: u64 = evicted.iter().map(|&i| self.pool.entries[i].tx.fee()).sum();
+		let evicted_weight: u64 = evicted
+			.iter()
+			.map(|&i| self.pool.entries[i].tx.weight() as u64)
+			.sum();
+
+		let new_fee = tx.fee();
+		let new_weight = tx.weight() as u64;
+
+		// Compare feerates (fee / weight) without actually dividing: integer
+		// division truncates small fees/weights down to the same 0, which
+		// would accept or reject replacements essentially at random. Instead
+		// cross-multiply in `u128` so the comparison is exact.
+		let higher_feerate =
+			new_fee as u128 * evicted_weight as u128 > evicted_fee as u128 * new_weight as u128;
+		if new_fee <= evicted_fee || !higher_feerate {
+			return Err(PoolError::ReplacementUnderpriced);
+		}
+
+		for input in tx.inputs() {
+			if let Some(owner) = self.pool.owner_of(&input.commit) {
+				if !evicted.contains(&owner) {
+					return Err(PoolError::ReplacementUnderpriced);
+				}
+			}
+		}
+
+		Ok(evicted.into_iter().collect())
+	}
+
+	/// Fully validate `tx` against the confirmed UTXO set plus any in-pool
+	/// ancestor it may be spending from.
+	fn validate_tx(&self, tx: &Transaction, header: &BlockHeader) -> Result<(), PoolError> {
+		// A standalone tx must clear the pool's minimum feerate on its own;
+		// `add_package_to_pool` deliberately doesn't call this, so a
+		// below-minimum parent can still ride into the pool on a
+		// high-feerate child's back.
+		if (tx.fee() as u128)
+			< self.config.accept_fee_base as u128 * tx.weight() as u128
+		{
+			return Err(PoolError::LowFeeTransaction(tx.fee()));
+		}
+
+		self.blockchain.verify_coinbase_maturity(tx)?;
+		self.blockchain.verify_tx_lock_height(tx)?;
+
+		let mut complete_inputs = self.blockchain.validate_tx(tx)?;
+		complete_inputs.extend(self.pool_outputs(header));
+
+		self.body_validator.validate_aggregate_body(
+			tx.inputs().to_vec(),
+			tx.outputs().to_vec(),
+			tx.kernels().to_vec(),
+			tx.offset,
+			Some(&complete_inputs),
+			Weighting::AsTransaction,
+			header.height,
+		)
+	}
+
+	/// Every output currently produced by a pool entry, wrapped as an
+	/// `OutputEx` so it can stand in as a "complete input" for a transaction
+	/// that spends it while it's still unconfirmed.
+	fn pool_outputs(&self, header: &BlockHeader) -> HashMap<Commitment, OutputEx> {
+		let mut outputs = HashMap::new();
+		for entry in &self.pool.entries {
+			for output in entry.tx.outputs() {
+				outputs.insert(
+					output.commit,
+					OutputEx {
+						output: *output,
+						height: header.height,
+						mmr_index: 0,
+					},
+				);
+			}
+		}
+		outputs
+	}
+
+	/// Submit a dependency bundle (e.g. a CPFP parent/child pair) as a
+	/// single atomic unit: the whole package is validated as one aggregated
+	/// body, so a member whose own feerate is below the pool minimum can
+	/// still be accepted when another member drags the combined package
+	/// feerate above the threshold. On success every member is inserted in
+	/// topological order; on any failure none are.
+	pub fn add_package_to_pool(
+		&mut self,
+		src: TxSource,
+		txs: Vec<Transaction>,
+		header: &BlockHeader,
+	) -> Result<(), PoolError> {
+		let order = pool::order_package(&txs)?;
+
+		if self.pool.len() + txs.len() > self.config.max_pool_size {
+			return Err(PoolError::OverCapacity);
+		}
+
+		let total_weight: usize = txs.iter().map(|tx| tx.weight()).sum();
+		if total_weight > self.config.max_package_weight {
+			return Err(PoolError::InvalidPackage);
+		}
+
+		// The pool's minimum feerate applies to the package as a whole, not
+		// to each member on its own.
+		let total_fee: u64 = txs.iter().map(|tx| tx.fee()).sum();
+		if (total_fee as u128) < self.config.accept_fee_base as u128 * total_weight as u128 {
+			return Err(PoolError::LowFeeTransaction(total_fee));
+		}
+
+		// Packages are submitted atomically and don't go through the
+		// replacement machinery `add_to_pool` uses: a member that duplicates
+		// or double-spends an existing pool entry is rejected outright,
+		// rather than evicting anything.
+		for tx in &txs {
+			if self
+				.pool
+				.entries
+				.iter()
+				.any(|entry| entry.tx.kernels() == tx.kernels())
+			{
+				return Err(PoolError::DuplicateTx);
+			}
+			if !self.pool.find_conflicting_entries(tx).is_empty() {
+				return Err(PoolError::DoubleSpend);
+			}
+		}
+
+		for tx in &txs {
+			self.blockchain.verify_coinbase_maturity(tx)?;
+			self.blockchain.verify_tx_lock_height(tx)?;
+		}
+
+		let aggregate = transaction::aggregate(txs.clone())?;
+
+		let mut complete_inputs = self.blockchain.validate_tx(&aggregate)?;
+		complete_inputs.extend(self.pool_outputs(header));
+		for tx in &txs {
+			for output in tx.outputs() {
+				complete_inputs.insert(
+					output.commit,
+					OutputEx {
+						output: *output,
+						height: header.height,
+						mmr_index: 0,
+					},
+				);
+			}
+		}
+
+		self.body_validator.validate_aggregate_body(
+			aggregate.inputs().to_vec(),
+			aggregate.outputs().to_vec(),
+			aggregate.kernels().to_vec(),
+			aggregate.offset,
+			Some(&complete_inputs),
+			Weighting::AsTransaction,
+			header.height,
+		)?;
+
+		for idx in order {
+			self.fee_estimator.track(&txs[idx], header.height);
+			self.pool
+				.add_to_pool(PoolEntry::new(txs[idx].clone(), src.clone()));
+		}
+
+		Ok(())
+	}
+
+	/// Select the set of pool transactions a new block should include,
+	/// ordered to maximize fee revenue within `self.config.mineable_max_weight`.
+	/// See `Pool::cpfp_order` for the selection algorithm.
+	pub fn prepare_mineable_transactions(&self) -> Result<Vec<Transaction>, PoolError> {
+		Ok(self
+			.pool
+			.cpfp_order(self.config.mineable_max_weight)
+			.into_iter()
+			.map(|idx| self.pool.entries[idx].tx.clone())
+			.collect())
+	}
+
+	/// Remove transactions that have been confirmed in `block`, along with
+	/// any pool transaction that conflicts with it (double-spends an input
+	/// the block already spent) and that transaction's in-pool descendants,
+	/// which can no longer be valid without it.
+	pub fn reconcile_block(&mut self, block: &Block) -> Result<(), PoolError> {
+		let mined_inputs: HashSet<_> = block.inputs().iter().map(|input| input.commit).collect();
+		let mined_excesses: HashSet<_> = block.kernels().iter().map(|k| k.excess).collect();
+
+		let mut to_remove: HashSet<usize> = HashSet::new();
+		let mut confirmed: Vec<Transaction> = Vec::new();
+		for (idx, entry) in self.pool.entries.iter().enumerate() {
+			let is_confirmed = entry
+				.tx
+				.kernels()
+				.iter()
+				.any(|k| mined_excesses.contains(&k.excess));
+			if is_confirmed {
+				to_remove.insert(idx);
+				confirmed.push(entry.tx.clone());
+				continue;
+			}
+
+			let conflicts = entry
+				.tx
+				.inputs()
+				.iter()
+				.any(|input| mined_inputs.contains(&input.commit));
+			if conflicts {
+				to_remove.insert(idx);
+				to_remove.extend(self.pool.unconfirmed_descendants(idx));
+			}
+		}
+
+		// Re-run the same consensus checks `add_to_pool` already applied to
+		// each of these transactions individually, this time as a single
+		// aggregate, so a divergence between the per-tx and mined-block
+		// paths can't let an invalid combination slip through (see
+		// `test_reconcile_block_rejects_invalid_confirmed_aggregate`). We
+		// validate the confirmed *pool* transactions, not the mined block
+		// body itself: the block body also carries the coinbase reward
+		// output/kernel and the block's own kernel offset, neither of which
+		// a plain transaction aggregate (which this pool never sees any
+		// coinbase for) can account for.
+		//
+		// `complete_inputs` is `None` here, unlike `add_to_pool`/
+		// `add_package_to_pool`: every remaining input the confirmed
+		// aggregate spends (after cut-through against its own outputs) was
+		// already consumed from the chain's UTXO set by the time this runs,
+		// so there's nothing left to look up. That only weakens checks that
+		// need the spent output's original data; the kernel-sum balance
+		// check below (what actually catches an inflated/unbalanced
+		// aggregate) doesn't depend on it either way.
+		if !confirmed.is_empty() {
+			let aggregate = transaction::aggregate(confirmed)?;
+			self.body_validator.validate_aggregate_body(
+				aggregate.inputs().to_vec(),
+				aggregate.outputs().to_vec(),
+				aggregate.kernels().to_vec(),
+				aggregate.offset,
+				None,
+				Weighting::AsTransaction,
+				block.header.height,
+			)?;
+		}
+
+		self.fee_estimator.process_block(
+			block.header.height,
+			&block.kernels().iter().map(|k| k.excess).collect::<Vec<_>>(),
+		);
+		for &idx in &to_remove {
+			if !self.pool.entries[idx]
+				.tx
+				.kernels()
+				.iter()
+				.any(|k| mined_excesses.contains(&k.excess))
+			{
+				self.fee_estimator.untrack(&self.pool.entries[idx].tx);
+			}
+		}
+
+		self.pool.remove_by_indices(to_remove.into_iter().collect());
+		Ok(())
+	}
+}
@@ -0,0 +1,148 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The types that are passed into the pool or expected to be returned out
+//! of it.
+
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+use gotts_core::core::hash::Hash;
+use gotts_core::core::{transaction, BlockHeader, OutputEx, Transaction};
+use gotts_util::secp::pedersen::Commitment;
+
+/// Transaction pool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+	/// Base fee that's accepted into the pool, in units of fee per weight.
+	/// A transaction submitted on its own with a lower feerate is rejected
+	/// outright; a package submitted via `add_package_to_pool` is held to
+	/// this minimum by its combined fee and weight, not member by member.
+	pub accept_fee_base: u64,
+	/// Maximum capacity of the pool, in number of transactions.
+	pub max_pool_size: usize,
+	/// Maximum total weight (in "weight" units, see `Transaction::weight`) of
+	/// the transactions a single `prepare_mineable_transactions` call may
+	/// return.
+	pub mineable_max_weight: usize,
+	/// Maximum total weight of the member transactions a single
+	/// `add_package_to_pool` call may submit.
+	pub max_package_weight: usize,
+}
+
+impl Default for PoolConfig {
+	fn default() -> PoolConfig {
+		PoolConfig {
+			accept_fee_base: 1,
+			max_pool_size: 50_000,
+			mineable_max_weight: 40_000,
+			max_package_weight: 80_000,
+		}
+	}
+}
+
+/// Placeholder: the reason a tx was added to the pool, kept around so we can
+/// attribute misbehaving peers and for debugging/logging purposes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxSource {
+	/// Human-readable name used for logging and errors.
+	pub debug_name: String,
+	/// Unique identifier used to distinguish this peer from others.
+	pub identifier: String,
+}
+
+/// A single pool entry, a transaction tracked by the pool along with the
+/// accounting metadata the pool needs to order and evict it.
+#[derive(Clone, Debug)]
+pub struct PoolEntry {
+	/// Where this tx originated from.
+	pub src: TxSource,
+	/// The time this tx was added to the pool.
+	pub tx_at: DateTime<Utc>,
+	/// The transaction itself.
+	pub tx: Transaction,
+}
+
+impl PoolEntry {
+	/// Construct a new pool entry around a transaction, with a given source.
+	pub fn new(tx: Transaction, src: TxSource) -> PoolEntry {
+		PoolEntry {
+			src,
+			tx_at: Utc::now(),
+			tx,
+		}
+	}
+}
+
+/// Placeholder for the chain-facing trait, abstracting the txpool from the
+/// parent chain implementation. See the real implementation in the chain
+/// crate and the test adapter under `tests/common`.
+pub trait BlockChain: Sync + Send {
+	/// Get the header at the head of the most work chain.
+	fn chain_head(&self) -> Result<BlockHeader, PoolError>;
+
+	/// Get a block header by hash.
+	fn get_block_header(&self, hash: &Hash) -> Result<BlockHeader, PoolError>;
+
+	/// Fully validate a transaction against the current UTXO set, returning
+	/// the full set of "complete" (pre-existing) inputs it spends.
+	fn validate_tx(&self, tx: &Transaction) -> Result<HashMap<Commitment, OutputEx>, PoolError>;
+
+	/// Verify any coinbase outputs being spent have matured sufficiently.
+	fn verify_coinbase_maturity(&self, tx: &Transaction) -> Result<(), PoolError>;
+
+	/// Verify any absolute/relative time locks on a tx have been reached.
+	fn verify_tx_lock_height(&self, tx: &Transaction) -> Result<(), PoolError>;
+}
+
+/// Possible errors when interacting with the transaction pool.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PoolError {
+	/// An invalid pool entry caused by underlying tx validation error.
+	InvalidTx(transaction::Error),
+	/// Attempt to add a transaction to the pool with lock_height exceeding
+	/// height of current block.
+	ImmatureTransaction,
+	/// Attempt to spend a coinbase output before it has sufficiently matured.
+	ImmatureCoinbase,
+	/// Transaction pool is over capacity, can't accept more transactions.
+	OverCapacity,
+	/// Transaction feerate is too low to be accepted into the pool.
+	LowFeeTransaction(u64),
+	/// Attempt to add a duplicate tx to the pool.
+	DuplicateTx,
+	/// A member of a package submitted via `add_package_to_pool` spends an
+	/// input already spent by another pool transaction (packages never
+	/// replace existing entries; `add_to_pool` reports a rejected
+	/// replacement as `ReplacementUnderpriced` instead).
+	DoubleSpend,
+	/// A replacement transaction did not pay enough, relative to the
+	/// transaction(s) (and their in-pool descendants) it would evict, to
+	/// satisfy the pool's BIP125-style replace-by-fee rules.
+	ReplacementUnderpriced,
+	/// A package submitted via `add_package_to_pool` was empty, exceeded the
+	/// configured package weight cap, or its members did not form a single
+	/// connected, acyclic ancestor set.
+	InvalidPackage,
+	/// Other kinds of error (not yet pulled out into meaningful errors).
+	Other(String),
+}
+
+impl From<transaction::Error> for PoolError {
+	fn from(e: transaction::Error) -> PoolError {
+		PoolError::InvalidTx(e)
+	}
+}
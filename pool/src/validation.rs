@@ -0,0 +1,104 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A single validation routine for an aggregated transaction body, shared
+//! by `TransactionPool::add_to_pool` (and `add_package_to_pool`) on one
+//! side and `TransactionPool::reconcile_block` on the other, so "valid in
+//! the mempool" and "valid in a block" can't silently drift apart. The
+//! same `PoolBodyValidation` trait can be implemented by block-sync body
+//! validation to reuse the exact same rules.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use gotts_core::core::verifier_cache::VerifierCache;
+use gotts_core::core::{BlindingFactor, Input, Output, OutputEx, Transaction, TxKernel, Weighting};
+use gotts_util::secp::pedersen::Commitment;
+use gotts_util::RwLock;
+
+use crate::types::PoolError;
+
+/// Validates an aggregate transaction body: kernel sum balance, range
+/// proof and kernel signature checks (via a verifier cache), weight
+/// limits, and duplicate-commitment detection.
+pub trait PoolBodyValidation {
+	/// Validate the aggregate of `inputs`, `outputs` and `kernels` as a
+	/// single body against consensus rules. `complete_inputs` supplies the
+	/// pre-existing output each input spends, where available; pass `None`
+	/// when that context can't be reconstructed (e.g. reconciling a block
+	/// whose spent outputs have already left the UTXO set) to still run
+	/// every check that doesn't depend on it.
+	///
+	/// `offset` is the aggregate body's own blinding offset (a plain
+	/// transaction's `Transaction::offset`, or a block's combined kernel
+	/// offset): it factors into the kernel-sum balance check just like the
+	/// inputs, outputs and kernels do, so it must be the caller's, not a
+	/// default.
+	fn validate_aggregate_body(
+		&self,
+		inputs: Vec<Input>,
+		outputs: Vec<Output>,
+		kernels: Vec<TxKernel>,
+		offset: BlindingFactor,
+		complete_inputs: Option<&HashMap<Commitment, OutputEx>>,
+		weighting: Weighting,
+		height: u64,
+	) -> Result<(), PoolError>;
+}
+
+/// The pool's `PoolBodyValidation` implementation: assembles the aggregate
+/// as a `Transaction` and runs it through the same `Transaction::validate`
+/// consensus checks used everywhere else in the codebase.
+pub struct AggregateBodyValidator {
+	/// Shared cache of already-verified range proofs and kernel signatures.
+	pub verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+}
+
+impl PoolBodyValidation for AggregateBodyValidator {
+	fn validate_aggregate_body(
+		&self,
+		inputs: Vec<Input>,
+		outputs: Vec<Output>,
+		kernels: Vec<TxKernel>,
+		offset: BlindingFactor,
+		complete_inputs: Option<&HashMap<Commitment, OutputEx>>,
+		weighting: Weighting,
+		height: u64,
+	) -> Result<(), PoolError> {
+		let mut seen: HashSet<Commitment> = HashSet::new();
+		for output in &outputs {
+			if !seen.insert(output.commit) {
+				return Err(PoolError::Other(format!(
+					"duplicate output commitment {:?} in aggregate body",
+					output.commit
+				)));
+			}
+		}
+		seen.clear();
+		for input in &inputs {
+			if !seen.insert(input.commit) {
+				return Err(PoolError::Other(format!(
+					"duplicate input commitment {:?} in aggregate body",
+					input.commit
+				)));
+			}
+		}
+
+		let aggregate = Transaction::new(inputs, outputs, kernels).with_offset(offset);
+		aggregate
+			.validate(weighting, self.verifier_cache.clone(), complete_inputs, height)
+			.map_err(PoolError::InvalidTx)
+	}
+}
@@ -0,0 +1,136 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::core::Weighting;
+use self::pool::types::{PoolEntry, PoolError};
+use self::pool::PoolBodyValidation;
+use crate::common::*;
+use gotts_core as core;
+use gotts_pool as pool;
+use gotts_util as util;
+
+#[test]
+fn test_add_to_pool_and_reconcile_block_share_validation() {
+	util::init_test_logger();
+
+	let db_root = ".gotts_aggregate_body_validation".to_string();
+	clean_output_dir(db_root.clone());
+
+	{
+		let (keychain, mut chain, header, pool) = test_chain_and_pool(&db_root, vec![10, 20, 30]);
+
+		// A CPFP-linked parent/child pair, both mined in the same block: the
+		// child's input resolves to the parent's output, which only exists
+		// within that one block.
+		let root_tx = test_transaction(&keychain, vec![10, 20], vec![24]);
+		let child_tx = test_transaction(&keychain, vec![24], vec![20]);
+
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), root_tx.clone(), false, &header)
+				.unwrap();
+			write_pool
+				.add_to_pool(test_source(), child_tx.clone(), false, &header)
+				.unwrap();
+		}
+
+		let txs = pool.read().prepare_mineable_transactions().unwrap();
+		let block = add_block(&keychain, header, txs, &mut chain);
+
+		{
+			let mut write_pool = pool.write();
+			// reconcile_block now runs the confirmed pool transactions
+			// through the same `validate_aggregate_body` routine
+			// `add_to_pool` used; a block containing exactly what the pool
+			// already accepted must still reconcile cleanly.
+			write_pool.reconcile_block(&block).unwrap();
+			assert_eq!(write_pool.total_size(), 0);
+		}
+
+		// An inflated transaction is rejected identically whether it's
+		// submitted to the pool directly or fed through the shared
+		// validator reconcile_block now uses.
+		let header = block.header;
+		let bad_tx = test_bad_transaction(&keychain, vec![30], vec![29]);
+		{
+			let mut write_pool = pool.write();
+			assert_eq!(
+				write_pool.add_to_pool(test_source(), bad_tx.clone(), false, &header),
+				Err(PoolError::InvalidTx(
+					core::core::transaction::Error::TransactionSumMismatch
+				)),
+			);
+
+			let err = write_pool
+				.body_validator
+				.validate_aggregate_body(
+					bad_tx.inputs().to_vec(),
+					bad_tx.outputs().to_vec(),
+					bad_tx.kernels().to_vec(),
+					bad_tx.offset,
+					None,
+					Weighting::AsTransaction,
+					header.height,
+				)
+				.unwrap_err();
+			assert_eq!(
+				err,
+				PoolError::InvalidTx(core::core::transaction::Error::TransactionSumMismatch)
+			);
+		}
+	}
+	clean_output_dir(db_root.clone());
+}
+
+#[test]
+fn test_reconcile_block_rejects_invalid_confirmed_aggregate() {
+	util::init_test_logger();
+
+	let db_root = ".gotts_aggregate_body_validation_reconcile".to_string();
+	clean_output_dir(db_root.clone());
+
+	{
+		let (keychain, mut chain, header, pool) = test_chain_and_pool(&db_root, vec![10, 20, 30]);
+
+		// Seed the pool directly with an inflated tx, standing in for
+		// whatever bug let one slip past `add_to_pool`'s own check: this
+		// proves `reconcile_block`'s validation call is itself load-bearing,
+		// not just the one `add_to_pool` already runs.
+		let bad_tx = test_bad_transaction(&keychain, vec![30], vec![29]);
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.pool
+				.add_to_pool(PoolEntry::new(bad_tx.clone(), test_source()));
+		}
+
+		let block = add_block(&keychain, header, vec![bad_tx], &mut chain);
+		{
+			let mut write_pool = pool.write();
+			assert_eq!(
+				write_pool.reconcile_block(&block),
+				Err(PoolError::InvalidTx(
+					core::core::transaction::Error::TransactionSumMismatch
+				)),
+			);
+			// Reconciliation bailed out before removing anything.
+			assert_eq!(write_pool.total_size(), 1);
+		}
+	}
+	clean_output_dir(db_root.clone());
+}
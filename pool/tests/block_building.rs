@@ -15,105 +15,22 @@
 
 pub mod common;
 
-use self::core::core::hash::Hashed;
-use self::core::core::verifier_cache::LruVerifierCache;
-use self::core::core::{Block, BlockHeader, OutputEx, Transaction, Weighting};
-use self::core::libtx;
-use self::core::libtx::build;
-use self::core::libtx::ProofBuilder;
-use self::core::pow::Difficulty;
-use self::keychain::{ExtKeychain, Identifier, Keychain};
 use self::pool::types::PoolError;
-use self::util::secp::pedersen::Commitment;
-use self::util::RwLock;
 use crate::common::*;
 use gotts_core as core;
-use gotts_keychain as keychain;
 use gotts_pool as pool;
 use gotts_util as util;
-use std::collections::HashMap;
-use std::sync::Arc;
 
 #[test]
 fn test_transaction_pool_block_building() {
 	util::init_test_logger();
-	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
-	let builder = ProofBuilder::new(&keychain, &Identifier::zero());
 
 	let db_root = ".gotts_block_building".to_string();
 	clean_output_dir(db_root.clone());
 
 	{
-		let mut chain = ChainAdapter::init(db_root.clone()).unwrap();
-
-		let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
-
-		// Initialize the chain/txhashset with an initial block
-		// so we have a non-empty UTXO set.
-		let add_block =
-			|prev_header: BlockHeader, txs: Vec<Transaction>, chain: &mut ChainAdapter| {
-				let height = prev_header.height + 1;
-				let key_id = ExtKeychain::derive_key_id(1, height as u32, 0, 0, 0);
-				let fee = txs.iter().map(|x| x.fee()).sum();
-				let reward = libtx::reward::output(
-					&keychain,
-					&libtx::ProofBuilder::new(&keychain, &Identifier::zero()),
-					&key_id,
-					fee,
-					false,
-				)
-				.unwrap();
-				let mut block = Block::new(&prev_header, txs, Difficulty::min(), reward).unwrap();
-
-				// Set the prev_root to the prev hash for testing purposes (no MMR to obtain a root from).
-				block.header.prev_root = prev_header.hash();
-
-				chain.update_db_for_block(&block);
-				block
-			};
-
-		let block = add_block(BlockHeader::default(), vec![], &mut chain);
-		let header = block.header;
-
-		// Now create tx to spend that first coinbase (now matured).
-		// Provides us with some useful outputs to test with.
-		let initial_tx = test_transaction_spending_coinbase(
-			&keychain,
-			&header,
-			vec![10, 20, 30, 40, 59_000_000_000],
-		);
-
-		let mut complete_inputs: HashMap<Commitment, OutputEx> = HashMap::new();
-		let key_id1 = ExtKeychain::derive_key_id(1, header.height as u32, 0, 0, 0);
-		let (pre_tx, _) = build::partial_transaction(
-			vec![build::output(60_000_000_000, Some(0i64), key_id1)],
-			&keychain,
-			&builder,
-		)
-		.unwrap();
-		complete_inputs.insert(
-			pre_tx.body.outputs[0].commit,
-			OutputEx {
-				output: pre_tx.body.outputs[0],
-				height: header.height,
-				mmr_index: 1, // wrong index but not used here
-			},
-		);
-		initial_tx
-			.validate(
-				Weighting::AsTransaction,
-				verifier_cache.clone(),
-				Some(&complete_inputs),
-				1,
-			)
-			.unwrap();
-
-		// Mine that initial tx so we can spend it with multiple txs
-		let block = add_block(header, vec![initial_tx], &mut chain);
-		let header = block.header;
-
-		// Initialize a new pool with our chain adapter.
-		let pool = RwLock::new(test_setup(Arc::new(chain.clone()), verifier_cache));
+		let (keychain, mut chain, header, pool) =
+			test_chain_and_pool(&db_root, vec![10, 20, 30, 40, 59_000_000_000]);
 
 		let root_tx_1 = test_transaction(&keychain, vec![10, 20], vec![24]);
 		let root_tx_2 = test_transaction(&keychain, vec![30], vec![28]);
@@ -149,7 +66,7 @@ fn test_transaction_pool_block_building() {
 
 		let txs = pool.read().prepare_mineable_transactions().unwrap();
 
-		let block = add_block(header, txs, &mut chain);
+		let block = add_block(&keychain, header, txs, &mut chain);
 
 		// Check the block contains what we expect.
 		assert_eq!(block.inputs().len(), 4);
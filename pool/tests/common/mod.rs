@@ -0,0 +1,268 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common test harness shared by the pool crate's integration tests: a
+//! minimal in-memory `BlockChain` plus helpers to build the coinbase-spending
+//! and plain transactions the tests exercise the pool with.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+use self::core::core::hash::{Hash, Hashed};
+use self::core::core::verifier_cache::{LruVerifierCache, VerifierCache};
+use self::core::core::{Block, BlockHeader, OutputEx, Transaction, Weighting};
+use self::core::libtx;
+use self::core::libtx::{build, ProofBuilder};
+use self::core::pow::Difficulty;
+use self::keychain::{ExtKeychain, Identifier, Keychain};
+use self::pool::types::{BlockChain, PoolConfig, PoolError, TxSource};
+use self::pool::TransactionPool;
+use self::util::secp::pedersen::Commitment;
+use self::util::RwLock;
+use gotts_core as core;
+use gotts_keychain as keychain;
+use gotts_pool as pool;
+use gotts_util as util;
+
+/// A minimal in-memory chain adapter, good enough to drive the pool's
+/// integration tests: it tracks the confirmed UTXO set and the chain head,
+/// without any of the real node's persistence or consensus checks.
+#[derive(Clone)]
+pub struct ChainAdapter {
+	utxo: Arc<RwLock<HashMap<Commitment, OutputEx>>>,
+	header: Arc<RwLock<BlockHeader>>,
+}
+
+impl ChainAdapter {
+	/// Stand up a fresh adapter, creating the (unused beyond cleanup) db
+	/// directory the way the real chain's txhashset store would.
+	pub fn init(db_root: String) -> Result<ChainAdapter, String> {
+		fs::create_dir_all(&db_root).map_err(|e| e.to_string())?;
+		Ok(ChainAdapter {
+			utxo: Arc::new(RwLock::new(HashMap::new())),
+			header: Arc::new(RwLock::new(BlockHeader::default())),
+		})
+	}
+
+	/// Apply `block` to our view of the chain: drop spent outputs, record
+	/// new ones, and advance the stored header.
+	pub fn update_db_for_block(&self, block: &Block) {
+		let mut utxo = self.utxo.write();
+		for input in block.inputs() {
+			utxo.remove(&input.commit);
+		}
+		for output in block.outputs() {
+			utxo.insert(
+				output.commit,
+				OutputEx {
+					output: *output,
+					height: block.header.height,
+					mmr_index: 0,
+				},
+			);
+		}
+		*self.header.write() = block.header.clone();
+	}
+}
+
+impl BlockChain for ChainAdapter {
+	fn chain_head(&self) -> Result<BlockHeader, PoolError> {
+		Ok(self.header.read().clone())
+	}
+
+	fn get_block_header(&self, _hash: &Hash) -> Result<BlockHeader, PoolError> {
+		Ok(self.header.read().clone())
+	}
+
+	fn validate_tx(&self, tx: &Transaction) -> Result<HashMap<Commitment, OutputEx>, PoolError> {
+		let utxo = self.utxo.read();
+		let mut complete_inputs = HashMap::new();
+		for input in tx.inputs() {
+			if let Some(out) = utxo.get(&input.commit) {
+				complete_inputs.insert(input.commit, out.clone());
+			}
+		}
+		Ok(complete_inputs)
+	}
+
+	fn verify_coinbase_maturity(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		Ok(())
+	}
+
+	fn verify_tx_lock_height(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		Ok(())
+	}
+}
+
+/// Remove the on-disk state created by `ChainAdapter::init`.
+pub fn clean_output_dir(db_root: String) {
+	let _ = fs::remove_dir_all(db_root);
+}
+
+/// A `TxSource` good enough for tests, where we don't care who sent it.
+pub fn test_source() -> TxSource {
+	TxSource {
+		debug_name: "test".to_string(),
+		identifier: "127.0.0.1".to_string(),
+	}
+}
+
+/// Stand up a pool backed by `chain`.
+pub fn test_setup(
+	chain: Arc<ChainAdapter>,
+	verifier_cache: Arc<RwLock<dyn VerifierCache>>,
+) -> TransactionPool<ChainAdapter> {
+	TransactionPool::new(PoolConfig::default(), chain, verifier_cache)
+}
+
+/// Mine `txs` into a new block on top of `prev_header`, rewarding `keychain`,
+/// and apply it to `chain`.
+pub fn add_block(
+	keychain: &ExtKeychain,
+	prev_header: BlockHeader,
+	txs: Vec<Transaction>,
+	chain: &mut ChainAdapter,
+) -> Block {
+	let height = prev_header.height + 1;
+	let key_id = ExtKeychain::derive_key_id(1, height as u32, 0, 0, 0);
+	let fee = txs.iter().map(|x| x.fee()).sum();
+	let reward = libtx::reward::output(
+		keychain,
+		&ProofBuilder::new(keychain, &Identifier::zero()),
+		&key_id,
+		fee,
+		false,
+	)
+	.unwrap();
+	let mut block = Block::new(&prev_header, txs, Difficulty::min(), reward).unwrap();
+	// Set the prev_root to the prev hash for testing purposes (no MMR to obtain a root from).
+	block.header.prev_root = prev_header.hash();
+	chain.update_db_for_block(&block);
+	block
+}
+
+/// Stand up the scaffolding every integration test in this crate starts
+/// from: a chain with one matured coinbase spent into `output_values`, and a
+/// pool backed by it. Returns the keychain the outputs were built with (so
+/// callers can derive further test transactions), the chain (so callers can
+/// mine further blocks), the header of the chain tip, and the pool.
+pub fn test_chain_and_pool(
+	db_root: &str,
+	output_values: Vec<u64>,
+) -> (
+	ExtKeychain,
+	ChainAdapter,
+	BlockHeader,
+	RwLock<TransactionPool<ChainAdapter>>,
+) {
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+	let mut chain = ChainAdapter::init(db_root.to_string()).unwrap();
+	let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+
+	let block = add_block(&keychain, BlockHeader::default(), vec![], &mut chain);
+	let header = block.header;
+
+	// Spend that first coinbase (now matured) so we have useful outputs to
+	// test with.
+	let initial_tx = test_transaction_spending_coinbase(&keychain, &header, output_values);
+	initial_tx
+		.validate(Weighting::AsTransaction, verifier_cache.clone(), None, 1)
+		.unwrap();
+
+	// Mine that initial tx so it can be spent by multiple further txs.
+	let block = add_block(&keychain, header, vec![initial_tx], &mut chain);
+	let header = block.header;
+
+	let pool = RwLock::new(test_setup(Arc::new(chain.clone()), verifier_cache));
+	(keychain, chain, header, pool)
+}
+
+/// Build a transaction spending the coinbase output of `header`'s block into
+/// `output_values`, with the remainder going to the fee.
+pub fn test_transaction_spending_coinbase(
+	keychain: &ExtKeychain,
+	header: &BlockHeader,
+	output_values: Vec<u64>,
+) -> Transaction {
+	let coinbase_reward: u64 = 60_000_000_000;
+	let output_sum: u64 = output_values.iter().sum();
+	assert!(coinbase_reward >= output_sum);
+	let fees = coinbase_reward - output_sum;
+
+	let key_id1 = ExtKeychain::derive_key_id(1, header.height as u32, 0, 0, 0);
+	let mut tx_elements = vec![build::coinbase_input(coinbase_reward, key_id1)];
+
+	for output_value in output_values {
+		let key_id = ExtKeychain::derive_key_id(1, output_value as u32, 0, 0, 0);
+		tx_elements.push(build::output(output_value, None, key_id));
+	}
+
+	build::transaction(
+		tx_elements,
+		fees,
+		keychain,
+		&ProofBuilder::new(keychain, &Identifier::zero()),
+	)
+	.unwrap()
+}
+
+/// Build a plain transaction spending `input_values` into `output_values`,
+/// with the remainder going to the fee. Inputs/outputs are keyed
+/// deterministically off their value, so tests can spend an earlier test
+/// transaction's output just by reusing the same value.
+pub fn test_transaction(
+	keychain: &ExtKeychain,
+	input_values: Vec<u64>,
+	output_values: Vec<u64>,
+) -> Transaction {
+	let input_sum: u64 = input_values.iter().sum();
+	let output_sum: u64 = output_values.iter().sum();
+	assert!(input_sum >= output_sum);
+	let fees = input_sum - output_sum;
+
+	let mut tx_elements = Vec::new();
+	for input_value in input_values {
+		let key_id = ExtKeychain::derive_key_id(1, input_value as u32, 0, 0, 0);
+		tx_elements.push(build::input(input_value, key_id));
+	}
+	for output_value in output_values {
+		let key_id = ExtKeychain::derive_key_id(1, output_value as u32, 0, 0, 0);
+		tx_elements.push(build::output(output_value, None, key_id));
+	}
+
+	build::transaction(
+		tx_elements,
+		fees,
+		keychain,
+		&ProofBuilder::new(keychain, &Identifier::zero()),
+	)
+	.unwrap()
+}
+
+/// Build a transaction like `test_transaction`, but lie about the fee it
+/// actually pays so the kernel no longer balances against the committed
+/// input/output values: an inflation bug that `validate` must reject.
+pub fn test_bad_transaction(
+	keychain: &ExtKeychain,
+	input_values: Vec<u64>,
+	output_values: Vec<u64>,
+) -> Transaction {
+	let mut tx = test_transaction(keychain, input_values, output_values);
+	for kernel in &mut tx.body.kernels {
+		kernel.fee = kernel.fee.saturating_sub(1);
+	}
+	tx
+}
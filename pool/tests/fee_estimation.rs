@@ -0,0 +1,124 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::pool::FeeEstimator;
+use crate::common::*;
+use gotts_pool as pool;
+use gotts_util as util;
+
+#[test]
+fn test_fee_estimator_tracks_confirmations() {
+	util::init_test_logger();
+
+	let db_root = ".gotts_fee_estimation".to_string();
+	clean_output_dir(db_root.clone());
+
+	{
+		let (keychain, mut chain, header, pool) =
+			test_chain_and_pool(&db_root, vec![10, 20, 30, 40]);
+
+		let tx_1 = test_transaction(&keychain, vec![10], vec![8]);
+		let tx_2 = test_transaction(&keychain, vec![20], vec![18]);
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), tx_1.clone(), false, &header)
+				.unwrap();
+			write_pool
+				.add_to_pool(test_source(), tx_2.clone(), false, &header)
+				.unwrap();
+
+			// With no confirmations recorded yet, the estimator has nothing
+			// to go on.
+			assert_eq!(write_pool.fee_estimator.estimate_fee(1), None);
+		}
+
+		// Confirm both transactions in the very next block.
+		let txs = pool.read().prepare_mineable_transactions().unwrap();
+		let block = add_block(&keychain, header, txs, &mut chain);
+		{
+			let mut write_pool = pool.write();
+			write_pool.reconcile_block(&block).unwrap();
+			assert_eq!(write_pool.total_size(), 0);
+
+			// Both txs confirmed within 1 block, so a 1-block target should
+			// now resolve to the upper bound of the bucket holding what they
+			// paid.
+			let estimate = write_pool.fee_estimator.estimate_fee(1);
+			assert!(estimate.is_some());
+		}
+
+		// The rolling history survives a save/load round trip.
+		let snapshot_path = format!("{}/fee_estimator.json", db_root);
+		{
+			let write_pool = pool.write();
+			write_pool.fee_estimator.save(&snapshot_path).unwrap();
+		}
+		let reloaded =
+			FeeEstimator::load(Default::default(), &snapshot_path).expect("reload estimator");
+		assert_eq!(
+			reloaded.estimate_fee(1),
+			pool.read().fee_estimator.estimate_fee(1)
+		);
+	}
+	clean_output_dir(db_root.clone());
+}
+
+/// Two single-input/output transactions of nearly identical weight but
+/// wildly different fees must resolve to different feerate estimates. A
+/// plain `fee / weight` truncates both down to the same bucket-0 floor,
+/// which is exactly the bug this test guards against.
+#[test]
+fn test_fee_estimator_differentiates_feerates() {
+	util::init_test_logger();
+
+	let estimate_for_fee = |db_root: &str, fee: u64| -> Option<u64> {
+		clean_output_dir(db_root.to_string());
+		let (keychain, mut chain, header, pool) = test_chain_and_pool(db_root, vec![100_000]);
+
+		let tx = test_transaction(&keychain, vec![100_000], vec![100_000 - fee]);
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), tx, false, &header)
+				.unwrap();
+		}
+
+		let txs = pool.read().prepare_mineable_transactions().unwrap();
+		let block = add_block(&keychain, header, txs, &mut chain);
+		{
+			let mut write_pool = pool.write();
+			write_pool.reconcile_block(&block).unwrap();
+		}
+
+		let estimate = pool.read().fee_estimator.estimate_fee(1);
+		clean_output_dir(db_root.to_string());
+		estimate
+	};
+
+	let low = estimate_for_fee(".gotts_fee_estimation_low", 1);
+	let high = estimate_for_fee(".gotts_fee_estimation_high", 5_000);
+
+	assert!(low.is_some());
+	assert!(high.is_some());
+	assert!(
+		high > low,
+		"a 5000x higher fee must land in a higher feerate bucket, got low={:?} high={:?}",
+		low,
+		high
+	);
+}
@@ -0,0 +1,107 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::pool::types::PoolError;
+use crate::common::*;
+use gotts_pool as pool;
+use gotts_util as util;
+
+#[test]
+fn test_transaction_pool_package_submission() {
+	util::init_test_logger();
+
+	let db_root = ".gotts_package_submission".to_string();
+	clean_output_dir(db_root.clone());
+
+	{
+		let (keychain, _chain, header, pool) =
+			test_chain_and_pool(&db_root, vec![10, 20, 30, 40, 50]);
+
+		// A parent spending `10` and a child spending the parent's output,
+		// submitted together as an atomic CPFP bundle.
+		let root_tx = test_transaction(&keychain, vec![10, 20], vec![24]);
+		let child_tx = test_transaction(&keychain, vec![24], vec![20]);
+
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_package_to_pool(
+					test_source(),
+					vec![root_tx.clone(), child_tx.clone()],
+					&header,
+				)
+				.unwrap();
+
+			assert_eq!(write_pool.total_size(), 2);
+			// The parent must have landed before the child.
+			let root_pos = write_pool
+				.pool
+				.entries
+				.iter()
+				.position(|e| e.tx.kernels() == root_tx.kernels())
+				.unwrap();
+			let child_pos = write_pool
+				.pool
+				.entries
+				.iter()
+				.position(|e| e.tx.kernels() == child_tx.kernels())
+				.unwrap();
+			assert!(root_pos < child_pos);
+		}
+
+		// An already-accepted, unrelated pool entry spending `30`.
+		let existing_tx = test_transaction(&keychain, vec![30], vec![28]);
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), existing_tx.clone(), false, &header)
+				.unwrap();
+			assert_eq!(write_pool.total_size(), 3);
+		}
+
+		// A package whose root re-spends the `30` output already consumed by
+		// `existing_tx` is rejected atomically: neither member is inserted.
+		let bad_root = test_transaction(&keychain, vec![30], vec![27]);
+		let bad_child = test_transaction(&keychain, vec![27], vec![20]);
+		{
+			let mut write_pool = pool.write();
+			assert_eq!(
+				write_pool.add_package_to_pool(test_source(), vec![bad_root, bad_child], &header),
+				Err(PoolError::DoubleSpend),
+			);
+			assert_eq!(write_pool.total_size(), 3);
+		}
+
+		// A package made of two unrelated, disconnected transactions is
+		// rejected outright.
+		let unrelated_a = test_transaction(&keychain, vec![40], vec![38]);
+		let unrelated_b = test_transaction(&keychain, vec![50], vec![48]);
+		{
+			let mut write_pool = pool.write();
+			assert_eq!(
+				write_pool.add_package_to_pool(
+					test_source(),
+					vec![unrelated_a, unrelated_b],
+					&header,
+				),
+				Err(PoolError::InvalidPackage),
+			);
+			assert_eq!(write_pool.total_size(), 3);
+		}
+	}
+	clean_output_dir(db_root.clone());
+}
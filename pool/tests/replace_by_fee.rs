@@ -0,0 +1,79 @@
+// Copyright 2018 The Grin Developers
+// Modifications Copyright 2019 The Gotts Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use crate::common::*;
+use gotts_pool as pool;
+use gotts_util as util;
+
+#[test]
+fn test_transaction_pool_replace_by_fee() {
+	util::init_test_logger();
+
+	let db_root = ".gotts_replace_by_fee".to_string();
+	clean_output_dir(db_root.clone());
+
+	{
+		let (keychain, _chain, header, pool) = test_chain_and_pool(&db_root, vec![10, 20, 30, 40]);
+
+		// A low-fee tx spending the `10` and `20` outputs.
+		let root_tx_1 = test_transaction(&keychain, vec![10, 20], vec![29]);
+
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), root_tx_1.clone(), false, &header)
+				.unwrap();
+			assert_eq!(write_pool.total_size(), 1);
+		}
+
+		// A replacement spending the same `10` output, paying a strictly
+		// higher absolute fee and a strictly higher feerate.
+		let replacement_tx = test_transaction(&keychain, vec![10, 20], vec![20]);
+
+		{
+			let mut write_pool = pool.write();
+			write_pool
+				.add_to_pool(test_source(), replacement_tx.clone(), false, &header)
+				.unwrap();
+
+			assert_eq!(write_pool.total_size(), 1);
+			assert!(write_pool
+				.pool
+				.entries
+				.iter()
+				.any(|entry| entry.tx.kernels() == replacement_tx.kernels()));
+			assert!(!write_pool
+				.pool
+				.entries
+				.iter()
+				.any(|entry| entry.tx.kernels() == root_tx_1.kernels()));
+		}
+
+		// An underpriced double-spend of the same input must be rejected,
+		// leaving the pool unchanged.
+		let underpriced_tx = test_transaction(&keychain, vec![10, 20], vec![28]);
+		{
+			let mut write_pool = pool.write();
+			assert_eq!(
+				write_pool.add_to_pool(test_source(), underpriced_tx, false, &header),
+				Err(pool::types::PoolError::ReplacementUnderpriced),
+			);
+			assert_eq!(write_pool.total_size(), 1);
+		}
+	}
+	clean_output_dir(db_root.clone());
+}